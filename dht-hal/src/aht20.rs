@@ -0,0 +1,263 @@
+//! A driver for the AHT20 humidity/temperature sensor, also sold (with an
+//! identical pinout and protocol) as the "DHT20".
+//!
+//! Unlike the [`Dht11`]/[`Dht22`] family, the AHT20 speaks I2C rather than
+//! the one-wire pulse protocol, so it's driven over an
+//! [`embedded_hal::blocking::i2c`] bus instead of a GPIO pin.
+//!
+//! [`Dht11`]: crate::Dht11
+//! [`Dht22`]: crate::Dht22
+use crate::{celcius_to_fahrenheit, dew_point_celcius};
+use embedded_hal::blocking::{delay::DelayMs, i2c};
+
+/// The AHT20/DHT20's fixed I2C address.
+pub const ADDRESS: u8 = 0x38;
+
+/// The "trigger measurement" command, per the datasheet.
+const TRIGGER_MEASUREMENT: [u8; 3] = [0xAC, 0x33, 0x00];
+
+/// An AHT20 (or DHT20) sensor, connected over I2C.
+#[derive(Debug)]
+pub struct Dht20<I2C, T> {
+    i2c: I2C,
+    timer: T,
+    temp_offset: f32,
+    humidity_offset: f32,
+}
+
+/// A reading from a [`Dht20`] sensor.
+#[derive(Copy, Clone, Debug)]
+pub struct Reading {
+    humidity_raw: u32,
+    temp_raw: u32,
+    temp_offset: f32,
+    humidity_offset: f32,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub struct Error<I>(ErrorKind<I>);
+
+#[derive(Eq, PartialEq, Debug)]
+enum ErrorKind<I> {
+    Io(I),
+    Crc { expected: u8, actual: u8 },
+    Busy,
+}
+
+impl<I2C, T, E> Dht20<I2C, T>
+where
+    I2C: i2c::Write<Error = E> + i2c::Read<Error = E>,
+    T: DelayMs<u16>,
+{
+    /// Returns a new AHT20/DHT20 sensor on the given I2C bus.
+    pub fn new(i2c: I2C, timer: T) -> Self {
+        Self {
+            i2c,
+            timer,
+            temp_offset: 0.0,
+            humidity_offset: 0.0,
+        }
+    }
+
+    /// Sets a calibration offset to add to every temperature reading, to
+    /// correct for a sensor that consistently reads high or low.
+    pub fn with_temp_offset(self, temp_offset: f32) -> Self {
+        Self {
+            temp_offset,
+            ..self
+        }
+    }
+
+    /// Sets a calibration offset to add to every humidity reading, to
+    /// correct for a sensor that consistently reads high or low.
+    pub fn with_humidity_offset(self, humidity_offset: f32) -> Self {
+        Self {
+            humidity_offset,
+            ..self
+        }
+    }
+
+    /// Triggers a measurement and blocks for the ~80ms the sensor needs to
+    /// complete it, then reads back the temperature and humidity.
+    pub fn read_blocking(&mut self) -> Result<Reading, Error<E>> {
+        self.i2c
+            .write(ADDRESS, &TRIGGER_MEASUREMENT)
+            .map_err(ErrorKind::from)?;
+        self.timer.delay_ms(80);
+
+        let mut bytes = [0u8; 7];
+        self.i2c.read(ADDRESS, &mut bytes).map_err(ErrorKind::from)?;
+
+        // Bit 7 of the status byte is set while the sensor is still busy
+        // measuring.
+        if bytes[0] & 0x80 != 0 {
+            return Err(ErrorKind::Busy.into());
+        }
+
+        let expected = bytes[6];
+        let actual = crc8(&bytes[..6]);
+        if actual != expected {
+            return Err(ErrorKind::Crc { expected, actual }.into());
+        }
+
+        // 20-bit humidity: bytes[1..3] plus the high nibble of bytes[3].
+        let humidity_raw =
+            (bytes[1] as u32) << 12 | (bytes[2] as u32) << 4 | (bytes[3] as u32) >> 4;
+        // 20-bit temperature: the low nibble of bytes[3] plus bytes[4..6].
+        let temp_raw = ((bytes[3] as u32) & 0x0f) << 16 | (bytes[4] as u32) << 8 | bytes[5] as u32;
+
+        Ok(Reading {
+            humidity_raw,
+            temp_raw,
+            temp_offset: self.temp_offset,
+            humidity_offset: self.humidity_offset,
+        })
+    }
+}
+
+impl Reading {
+    /// Returns the relative humidity, in percent, adjusted by the sensor's
+    /// calibration offset (see [`Dht20::with_humidity_offset`]), if any.
+    pub fn humidity_percent(self) -> f32 {
+        self.humidity_raw as f32 / (1u32 << 20) as f32 * 100.0 + self.humidity_offset
+    }
+
+    /// Returns the temperature in Celcius, adjusted by the sensor's
+    /// calibration offset (see [`Dht20::with_temp_offset`]), if any.
+    pub fn temp_celcius(self) -> f32 {
+        self.temp_raw as f32 / (1u32 << 20) as f32 * 200.0 - 50.0 + self.temp_offset
+    }
+
+    /// Returns the temperature in Fahrenheit.
+    pub fn temp_fahrenheit(self) -> f32 {
+        celcius_to_fahrenheit(self.temp_celcius())
+    }
+
+    /// Returns the dew point, in degrees Celcius. See
+    /// [`crate::Reading::dew_point_celcius`].
+    pub fn dew_point_celcius(self) -> f32 {
+        dew_point_celcius(self.temp_celcius(), self.humidity_percent())
+    }
+}
+
+impl<E> From<E> for ErrorKind<E> {
+    fn from(e: E) -> Self {
+        ErrorKind::Io(e)
+    }
+}
+
+impl<E> From<ErrorKind<E>> for Error<E> {
+    fn from(e: ErrorKind<E>) -> Self {
+        Self(e)
+    }
+}
+
+impl<E> Error<E> {
+    /// Returns `true` if an IO error occurred while reading from or writing
+    /// to the sensor over I2C.
+    pub fn is_io(&self) -> bool {
+        match self.0 {
+            ErrorKind::Io(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the reading from the sensor had a bad CRC.
+    pub fn is_crc(&self) -> bool {
+        match self.0 {
+            ErrorKind::Crc { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the sensor was still busy measuring when we tried
+    /// to read it back.
+    pub fn is_busy(&self) -> bool {
+        match self.0 {
+            ErrorKind::Busy => true,
+            _ => false,
+        }
+    }
+
+    /// If the error was caused by an underlying I2C error, returns it.
+    pub fn into_io(self) -> Option<E> {
+        match self.0 {
+            ErrorKind::Io(io) => Some(io),
+            _ => None,
+        }
+    }
+}
+
+/// CRC-8 with polynomial 0x31 and an initial value of 0xFF, as specified by
+/// the AHT20 datasheet.
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0xFFu8;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            if crc & 0x80 != 0 {
+                crc = (crc << 1) ^ 0x31;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+
+    /// A fake I2C bus that replays a scripted 7-byte reply to any `read`,
+    /// ignoring whatever gets `write`-ten (the trigger-measurement command
+    /// isn't meaningful without a real sensor behind it).
+    struct FakeI2c {
+        reply: [u8; 7],
+    }
+
+    impl i2c::Write for FakeI2c {
+        type Error = Infallible;
+        fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl i2c::Read for FakeI2c {
+        type Error = Infallible;
+        fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Infallible> {
+            buffer.copy_from_slice(&self.reply);
+            Ok(())
+        }
+    }
+
+    /// A fake timer whose delay is a no-op: the test doesn't need real time
+    /// to pass while `FakeI2c` has its reply ready immediately.
+    struct FakeTimer;
+
+    impl DelayMs<u16> for FakeTimer {
+        fn delay_ms(&mut self, _ms: u16) {}
+    }
+
+    // Status byte with the busy bit (0x80) clear, humidity_raw = 700_000,
+    // temp_raw = 600_000, and a matching CRC-8.
+    const GOOD_REPLY: [u8; 7] = [0x18, 0xAA, 0xE6, 0x09, 0x27, 0xC0, 0x9E];
+
+    #[test]
+    fn read_blocking_decodes_known_good_reply() {
+        let mut dht = Dht20::new(FakeI2c { reply: GOOD_REPLY }, FakeTimer);
+        let reading = dht.read_blocking().expect("checksum should be valid");
+        assert!((reading.humidity_percent() - 66.757_2).abs() < 1e-3);
+        assert!((reading.temp_celcius() - 64.440_92).abs() < 1e-3);
+    }
+
+    #[test]
+    fn read_blocking_rejects_bad_crc() {
+        let mut reply = GOOD_REPLY;
+        reply[6] = !reply[6]; // corrupt the trailing CRC byte
+        let mut dht = Dht20::new(FakeI2c { reply }, FakeTimer);
+        let err = dht.read_blocking().expect_err("CRC shouldn't match");
+        assert!(err.is_crc());
+    }
+}
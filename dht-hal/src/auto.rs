@@ -0,0 +1,183 @@
+//! Automatic DHT11-vs-DHT22 detection, for deployments where it's not known
+//! (or not worth tracking) which variant is wired up to a given pin.
+//!
+//! The one-wire protocol the two sensors speak is identical; only the
+//! startup timing and the decoding of the raw bytes differ. [`DhtAuto`]
+//! always uses the (longer, and safe for either sensor) DHT11 start delay,
+//! then inspects the first valid reading to guess which one it's actually
+//! talking to.
+use crate::kind;
+use crate::{Dht, Error, Reading};
+use embedded_hal::{blocking::delay, digital::v2 as digital};
+
+/// Which sensor kind a [`DhtAuto`] has determined it's talking to.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DetectedKind {
+    Dht11,
+    Dht22,
+}
+
+/// A DHT sensor that detects, on its first successful read, whether it's a
+/// DHT11 or a DHT22, and decodes every subsequent read accordingly.
+pub struct DhtAuto<P, T> {
+    dht: Dht<P, T, kind::Dht11>,
+    detected: Option<DetectedKind>,
+}
+
+/// A reading from a [`DhtAuto`] sensor, decoded using whichever kind was
+/// detected.
+#[derive(Copy, Clone, Debug)]
+pub enum AutoReading {
+    Dht11(Reading<kind::Dht11>),
+    Dht22(Reading<kind::Dht22>),
+}
+
+impl<P, T, E> DhtAuto<P, T>
+where
+    P: digital::InputPin<Error = E> + digital::OutputPin<Error = E>,
+    T: delay::DelayUs<u16> + delay::DelayMs<u16>,
+{
+    /// Returns a new auto-detecting DHT sensor.
+    pub fn new(pin: P, timer: T) -> Self {
+        Self {
+            dht: Dht::new(pin, timer),
+            detected: None,
+        }
+    }
+
+    /// Returns the detected sensor kind, once a reading with a valid
+    /// checksum has been obtained.
+    pub fn detected_kind(&self) -> Option<DetectedKind> {
+        self.detected
+    }
+
+    /// Reads from the sensor using blocking delays, detecting (and then
+    /// remembering) whether it's a DHT11 or a DHT22.
+    ///
+    /// Note that this is timing-critical, and should be run with interrupts
+    /// disabled, just like [`Dht::read_blocking`].
+    pub fn read_blocking(&mut self) -> Result<AutoReading, Error<E>> {
+        let reading = self.dht.read_blocking()?;
+        let kind = *self.detected.get_or_insert_with(|| reading.detect_kind());
+        Ok(match kind {
+            DetectedKind::Dht11 => AutoReading::Dht11(reading),
+            DetectedKind::Dht22 => AutoReading::Dht22(reading.reinterpret()),
+        })
+    }
+}
+
+impl AutoReading {
+    /// Returns which sensor kind this reading was decoded as.
+    pub fn kind(self) -> DetectedKind {
+        match self {
+            AutoReading::Dht11(_) => DetectedKind::Dht11,
+            AutoReading::Dht22(_) => DetectedKind::Dht22,
+        }
+    }
+
+    /// Returns the temperature in Celcius.
+    pub fn temp_celcius(self) -> f32 {
+        match self {
+            AutoReading::Dht11(reading) => reading.temp_celcius(),
+            AutoReading::Dht22(reading) => reading.temp_celcius(),
+        }
+    }
+
+    /// Returns the temperature in Fahrenheit.
+    pub fn temp_fahrenheit(self) -> f32 {
+        match self {
+            AutoReading::Dht11(reading) => reading.temp_fahrenheit(),
+            AutoReading::Dht22(reading) => reading.temp_fahrenheit(),
+        }
+    }
+
+    /// Returns the temperature in Kelvin.
+    pub fn temp_kelvin(self) -> f32 {
+        match self {
+            AutoReading::Dht11(reading) => reading.temp_kelvin(),
+            AutoReading::Dht22(reading) => reading.temp_kelvin(),
+        }
+    }
+
+    /// Returns the relative humidity, in percent.
+    pub fn humidity_percent(self) -> f32 {
+        match self {
+            AutoReading::Dht11(reading) => reading.humidity_percent(),
+            AutoReading::Dht22(reading) => reading.humidity_percent(),
+        }
+    }
+
+    /// Returns the dew point, in degrees Celcius. See
+    /// [`Reading::dew_point_celcius`].
+    pub fn dew_point_celcius(self) -> f32 {
+        match self {
+            AutoReading::Dht11(reading) => reading.dew_point_celcius(),
+            AutoReading::Dht22(reading) => reading.dew_point_celcius(),
+        }
+    }
+
+    /// Returns the heat index, in Fahrenheit. See
+    /// [`Reading::heat_index_fahrenheit`].
+    pub fn heat_index_fahrenheit(self) -> f32 {
+        match self {
+            AutoReading::Dht11(reading) => reading.heat_index_fahrenheit(),
+            AutoReading::Dht22(reading) => reading.heat_index_fahrenheit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{bits_of, FakePin, FakeTimer};
+    use core::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::iter::repeat_n;
+
+    /// Builds the `is_high` sequence `start_signal_blocking` and the
+    /// 40-bit `read_blocking` loop need to decode `bytes`, using a
+    /// zero-duration stand-in for the acknowledgement pulses (their exact
+    /// length doesn't affect decoding — only each bit's low-vs-high
+    /// comparison does).
+    fn is_high_sequence_for(bytes: [u8; 5]) -> VecDeque<bool> {
+        let mut seq = VecDeque::new();
+        seq.push_back(true); // end of the ack low pulse
+        seq.push_back(false); // end of the ack high pulse
+        for bit in bits_of(bytes) {
+            let (lo, hi) = if bit { (50, 70) } else { (50, 28) };
+            seq.extend(repeat_n(false, lo));
+            seq.push_back(true);
+            seq.extend(repeat_n(true, hi));
+            seq.push_back(false);
+        }
+        seq
+    }
+
+    fn detect(bytes: [u8; 5]) -> DetectedKind {
+        let pin = FakePin {
+            is_high: RefCell::new(is_high_sequence_for(bytes)),
+        };
+        let mut dht = DhtAuto::new(pin, FakeTimer);
+        dht.read_blocking().expect("checksum should be valid");
+        dht.detected_kind().expect("kind should be detected")
+    }
+
+    #[test]
+    fn detects_dht11_shaped_reading() {
+        // Humidity 45%, temperature 27C, decimal bytes 0.
+        assert_eq!(detect([0x2D, 0x00, 0x1B, 0x00, 0x48]), DetectedKind::Dht11);
+    }
+
+    #[test]
+    fn detects_dht22_shaped_reading() {
+        // Humidity 65.3%, temperature 23.1C.
+        assert_eq!(detect([0x02, 0x8D, 0x00, 0xE7, 0x76]), DetectedKind::Dht22);
+    }
+
+    #[test]
+    fn boundary_reading_is_still_dht11() {
+        // Humidity exactly 90%, temperature exactly 50C: `detect_kind`'s
+        // doc comment claims these are still within DHT11 range.
+        assert_eq!(detect([0x5A, 0x00, 0x32, 0x00, 0x8C]), DetectedKind::Dht11);
+    }
+}
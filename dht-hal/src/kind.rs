@@ -1,7 +1,12 @@
-pub trait DhtKind {
+pub trait DhtKind: Copy + core::fmt::Debug {
     #[doc(hidden)]
     const START_DELAY_US: u16;
 
+    /// The minimum interval, in milliseconds, between reads of this sensor
+    /// kind. Reading faster than this returns garbage or checksum errors.
+    #[doc(hidden)]
+    const MIN_INTERVAL_MS: u32;
+
     #[doc(hidden)]
     fn temp_celcius(integral: u8, decimal: u8) -> f32;
 
@@ -9,10 +14,12 @@ pub trait DhtKind {
     fn humidity_percent(integral: u8, decimal: u8) -> f32;
 }
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Dht11 {
     _p: (),
 }
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Dht22 {
     _p: (),
 }
@@ -21,6 +28,9 @@ impl DhtKind for Dht11 {
     // Datasheet says 20 ms.
     const START_DELAY_US: u16 = 20 * 1000;
 
+    // The DHT11 is a 1Hz sensor.
+    const MIN_INTERVAL_MS: u32 = 1000;
+
     fn temp_celcius(integral: u8, decimal: u8) -> f32 {
         // XXX(eliza): this is kind of copied from the Adafruit driver implementation,
         // which doesn't really explain what it's doing.
@@ -40,6 +50,9 @@ impl DhtKind for Dht22 {
     // Datasheet says "at least" 1 ms, so we'll delay for just over 1ms.
     const START_DELAY_US: u16 = 1100;
 
+    // The DHT22 is a 0.5Hz sensor.
+    const MIN_INTERVAL_MS: u32 = 2000;
+
     fn temp_celcius(integral: u8, decimal: u8) -> f32 {
         let mut temp = (((integral & 0x7F) as u16) << 8 | decimal as u16) as f32;
         temp *= 0.1;
@@ -5,8 +5,8 @@ use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct App {
     #[serde(default)]
-    listener: Listener,
-    sensors: HashMap<String, Sensor>,
+    pub(crate) listener: Listener,
+    pub(crate) sensors: HashMap<String, Sensor>,
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
@@ -18,11 +18,38 @@ pub struct Listener {
     port: u16,
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
-enum Sensor {
-    Dht11 { pin: u64 },
-    Dht22 { pin: u64 },
+pub(crate) enum Sensor {
+    Dht11 {
+        pin: u64,
+        #[serde(default)]
+        temp_offset: f32,
+        #[serde(default)]
+        humidity_offset: f32,
+    },
+    Dht22 {
+        pin: u64,
+        #[serde(default)]
+        temp_offset: f32,
+        #[serde(default)]
+        humidity_offset: f32,
+    },
+    /// A DHT20/AHT20, read over I2C rather than a GPIO pin.
+    Dht20 {
+        #[serde(default = "Sensor::default_i2c_address")]
+        address: u8,
+        #[serde(default)]
+        temp_offset: f32,
+        #[serde(default)]
+        humidity_offset: f32,
+    },
+}
+
+impl Sensor {
+    pub(crate) fn default_i2c_address() -> u8 {
+        dht_hal::aht20::ADDRESS
+    }
 }
 
 impl Listener {
@@ -84,8 +111,22 @@ mod tests {
         assert_eq!(sock.ip(), expected_ip);
         assert_eq!(sock.port(), expected_port);
         let expected_sensors: HashMap<_, _> = vec![
-            (String::from("foo"), Sensor::Dht11 { pin: foo_pin }),
-            (String::from("bar"), Sensor::Dht22 { pin: bar_pin }),
+            (
+                String::from("foo"),
+                Sensor::Dht11 {
+                    pin: foo_pin,
+                    temp_offset: 0.0,
+                    humidity_offset: 0.0,
+                },
+            ),
+            (
+                String::from("bar"),
+                Sensor::Dht22 {
+                    pin: bar_pin,
+                    temp_offset: 0.0,
+                    humidity_offset: 0.0,
+                },
+            ),
         ]
         .into_iter()
         .collect();
@@ -109,12 +150,99 @@ mod tests {
         let config: App = toml::from_str(toml.as_str()).unwrap();
         assert_eq!(config.listener, Listener::default());
         let expected_sensors: HashMap<_, _> = vec![
-            (String::from("foo"), Sensor::Dht11 { pin: foo_pin }),
-            (String::from("bar"), Sensor::Dht22 { pin: bar_pin }),
+            (
+                String::from("foo"),
+                Sensor::Dht11 {
+                    pin: foo_pin,
+                    temp_offset: 0.0,
+                    humidity_offset: 0.0,
+                },
+            ),
+            (
+                String::from("bar"),
+                Sensor::Dht22 {
+                    pin: bar_pin,
+                    temp_offset: 0.0,
+                    humidity_offset: 0.0,
+                },
+            ),
         ]
         .into_iter()
         .collect();
 
         assert_eq!(config.sensors, expected_sensors)
     }
+
+    #[test]
+    fn dht20_with_explicit_address() {
+        let address = 0x39;
+        let toml = format!(
+            "
+            [sensors]\n\
+            baz = {{ type = \"DHT20\", address = {address} }}\n\
+            ",
+            address = address,
+        );
+        let config: App = toml::from_str(toml.as_str()).unwrap();
+        let expected_sensors: HashMap<_, _> = vec![(
+            String::from("baz"),
+            Sensor::Dht20 {
+                address,
+                temp_offset: 0.0,
+                humidity_offset: 0.0,
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        assert_eq!(config.sensors, expected_sensors)
+    }
+
+    #[test]
+    fn dht20_default_address() {
+        let toml = "
+            [sensors]\n\
+            baz = { type = \"DHT20\" }\n\
+            ";
+        let config: App = toml::from_str(toml).unwrap();
+        let expected_sensors: HashMap<_, _> = vec![(
+            String::from("baz"),
+            Sensor::Dht20 {
+                address: Sensor::default_i2c_address(),
+                temp_offset: 0.0,
+                humidity_offset: 0.0,
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        assert_eq!(config.sensors, expected_sensors)
+    }
+
+    #[test]
+    fn calibration_offsets() {
+        let temp_offset = -1.5;
+        let humidity_offset = 2.0;
+        let toml = format!(
+            "
+            [sensors]\n\
+            foo = {{ type = \"DHT11\", pin = 4, temp_offset = {t}, humidity_offset = {h} }}\n\
+            ",
+            t = temp_offset,
+            h = humidity_offset,
+        );
+        let config: App = toml::from_str(toml.as_str()).unwrap();
+        let expected_sensors: HashMap<_, _> = vec![(
+            String::from("foo"),
+            Sensor::Dht11 {
+                pin: 4,
+                temp_offset,
+                humidity_offset,
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        assert_eq!(config.sensors, expected_sensors)
+    }
 }
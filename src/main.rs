@@ -0,0 +1,25 @@
+//! weathergirl: reads DHT11/DHT22/DHT20 sensors and serves their readings
+//! over HTTP, as configured by a TOML config file.
+mod config;
+mod server;
+
+use std::{env, fs, process};
+
+fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| "weathergirl.toml".to_string());
+
+    let contents = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("failed to read config file {}: {}", path, e);
+        process::exit(1);
+    });
+
+    let app: config::App = toml::from_str(&contents).unwrap_or_else(|e| {
+        eprintln!("failed to parse config file {}: {}", path, e);
+        process::exit(1);
+    });
+
+    if let Err(e) = server::run(app) {
+        eprintln!("server error: {}", e);
+        process::exit(1);
+    }
+}
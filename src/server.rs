@@ -0,0 +1,212 @@
+//! The network-server subsystem: periodically reads every sensor in the
+//! app's configuration and serves the latest readings over HTTP, on the
+//! configured [`Listener`](crate::config::Listener).
+//!
+//! Two endpoints are exposed:
+//!
+//! - `GET /readings` returns a JSON map of sensor name to the latest
+//!   temperature, humidity, and dew point.
+//! - `GET /metrics` returns the same data as Prometheus text-exposition
+//!   gauges (`weathergirl_temperature_celsius{sensor="..."}` and friends).
+use crate::config::{App, Sensor};
+use dht_hal::{Dht11, Dht20, Dht22};
+use linux_embedded_hal::{Delay, I2cdev, SysfsPin};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Response, Server as HttpServer};
+
+/// How often sensors are polled for a fresh reading.
+///
+/// This is the fastest any configured sensor kind supports (the DHT11's
+/// 1Hz); slower kinds are throttled down to their own minimum interval by
+/// [`dht_hal::Dht::read_throttled`], so polling this often doesn't make
+/// them read any faster than they actually can.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The I2C bus that DHT20/AHT20 sensors are assumed to be wired to.
+const I2C_BUS: &str = "/dev/i2c-1";
+
+/// The latest values read from a single configured sensor.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SensorReading {
+    pub temp_celcius: f32,
+    pub humidity_percent: f32,
+    pub dew_point_celcius: f32,
+}
+
+/// The latest reading for every configured sensor, by name.
+type Readings = Arc<Mutex<HashMap<String, SensorReading>>>;
+
+/// Binds `app`'s configured listener and serves its sensors' readings over
+/// HTTP until the process is killed or binding the listener fails.
+///
+/// Sensors are polled for new readings on a background thread, so the
+/// calling thread is free to block serving HTTP requests.
+pub fn run(app: App) -> io::Result<()> {
+    let readings: Readings = Arc::new(Mutex::new(HashMap::new()));
+    let socket_addr = app.listener.socket_addr();
+
+    {
+        let readings = Arc::clone(&readings);
+        thread::spawn(move || poll_sensors(app.sensors, readings));
+    }
+
+    let http = HttpServer::http(socket_addr).map_err(io::Error::other)?;
+    for request in http.incoming_requests() {
+        let readings = readings.lock().unwrap().clone();
+        let response = match request.url() {
+            "/readings" => {
+                let body = serde_json::to_string(&readings).unwrap_or_default();
+                Response::from_string(body).with_header(
+                    Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
+                )
+            }
+            "/metrics" => Response::from_string(render_metrics(&readings)).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4"[..])
+                    .unwrap(),
+            ),
+            _ => Response::from_string("not found").with_status_code(404),
+        };
+        let _ = request.respond(response);
+    }
+
+    Ok(())
+}
+
+/// Renders `readings` as Prometheus text-exposition gauges.
+fn render_metrics(readings: &HashMap<String, SensorReading>) -> String {
+    let mut out = String::new();
+    for (name, reading) in readings {
+        let _ = writeln!(
+            out,
+            "weathergirl_temperature_celsius{{sensor=\"{}\"}} {}",
+            name, reading.temp_celcius,
+        );
+        let _ = writeln!(
+            out,
+            "weathergirl_humidity_percent{{sensor=\"{}\"}} {}",
+            name, reading.humidity_percent,
+        );
+        let _ = writeln!(
+            out,
+            "weathergirl_dew_point_celsius{{sensor=\"{}\"}} {}",
+            name, reading.dew_point_celcius,
+        );
+    }
+    out
+}
+
+/// A live sensor driver, constructed from a single [`Sensor`] config entry.
+enum SensorDriver {
+    Dht11(Dht11<SysfsPin, Delay>),
+    Dht22(Dht22<SysfsPin, Delay>),
+    Dht20(Dht20<I2cdev, Delay>),
+}
+
+impl SensorDriver {
+    fn from_config(sensor: &Sensor) -> io::Result<Self> {
+        Ok(match *sensor {
+            Sensor::Dht11 {
+                pin,
+                temp_offset,
+                humidity_offset,
+            } => SensorDriver::Dht11(
+                Dht11::new(gpio_pin(pin)?, Delay)
+                    .with_temp_offset(temp_offset)
+                    .with_humidity_offset(humidity_offset),
+            ),
+            Sensor::Dht22 {
+                pin,
+                temp_offset,
+                humidity_offset,
+            } => SensorDriver::Dht22(
+                Dht22::new(gpio_pin(pin)?, Delay)
+                    .with_temp_offset(temp_offset)
+                    .with_humidity_offset(humidity_offset),
+            ),
+            Sensor::Dht20 {
+                address,
+                temp_offset,
+                humidity_offset,
+            } => SensorDriver::Dht20(
+                Dht20::new(i2c_bus(address)?, Delay)
+                    .with_temp_offset(temp_offset)
+                    .with_humidity_offset(humidity_offset),
+            ),
+        })
+    }
+
+    /// Reads the sensor, no more often than its kind's minimum sampling
+    /// interval allows. `now_ms` is the current time, in milliseconds, used
+    /// to throttle the DHT11/DHT22 sensors via
+    /// [`dht_hal::Dht::read_throttled`]; the DHT20 has no such throttle, as
+    /// it isn't generic over a [`dht_hal::kind::DhtKind`].
+    fn read(&mut self, now_ms: u32) -> Option<SensorReading> {
+        match self {
+            SensorDriver::Dht11(dht) => {
+                dht.read_throttled(now_ms).ok().map(|reading| SensorReading {
+                    temp_celcius: reading.temp_celcius(),
+                    humidity_percent: reading.humidity_percent(),
+                    dew_point_celcius: reading.dew_point_celcius(),
+                })
+            }
+            SensorDriver::Dht22(dht) => {
+                dht.read_throttled(now_ms).ok().map(|reading| SensorReading {
+                    temp_celcius: reading.temp_celcius(),
+                    humidity_percent: reading.humidity_percent(),
+                    dew_point_celcius: reading.dew_point_celcius(),
+                })
+            }
+            SensorDriver::Dht20(dht) => dht.read_blocking().ok().map(|reading| SensorReading {
+                temp_celcius: reading.temp_celcius(),
+                humidity_percent: reading.humidity_percent(),
+                dew_point_celcius: reading.dew_point_celcius(),
+            }),
+        }
+    }
+}
+
+fn gpio_pin(pin: u64) -> io::Result<SysfsPin> {
+    let pin = SysfsPin::new(pin);
+    pin.export()
+        .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+    Ok(pin)
+}
+
+fn i2c_bus(address: u8) -> io::Result<I2cdev> {
+    let mut i2c = I2cdev::new(I2C_BUS)?;
+    i2c.set_slave_address(u16::from(address))
+        .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+    Ok(i2c)
+}
+
+/// Polls every sensor in `sensors` every [`POLL_INTERVAL`], writing the
+/// latest readings into `readings`. Runs forever on its own thread.
+fn poll_sensors(sensors: HashMap<String, Sensor>, readings: Readings) {
+    let mut drivers: HashMap<String, SensorDriver> = sensors
+        .iter()
+        .filter_map(|(name, sensor)| match SensorDriver::from_config(sensor) {
+            Ok(driver) => Some((name.clone(), driver)),
+            Err(e) => {
+                eprintln!("failed to set up sensor {}: {}", name, e);
+                None
+            }
+        })
+        .collect();
+
+    let start = Instant::now();
+    loop {
+        let now_ms = start.elapsed().as_millis() as u32;
+        for (name, driver) in &mut drivers {
+            if let Some(reading) = driver.read(now_ms) {
+                readings.lock().unwrap().insert(name.clone(), reading);
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}